@@ -4,14 +4,19 @@
 //! `--recursive`. Once duplicates are found the user is prompted whether to delete them or not.
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use glob::Pattern;
 use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{self, File},
     io::{self, Read, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
 };
 use tabled::{
     Table, Tabled,
@@ -30,10 +35,58 @@ const CHUNK_SIZE: usize = 1024 * 1024;
 struct Args {
     #[arg(required = true, help = "Directory(s)")]
     dirs: Vec<PathBuf>,
-    #[arg(short = 'x', long, help = "Cross check across directories")]
+    #[arg(
+        short = 'x',
+        long,
+        help = "Only report duplicate groups that span more than one directory, but remove every duplicate in a \
+                qualifying group including ones that share a directory with another"
+    )]
     cross: bool,
     #[arg(short, long, help = "Recursively check directories")]
     recursive: bool,
+    #[arg(long, value_name = "BYTES", help = "Skip files smaller than this size")]
+    min_size: Option<u64>,
+    #[arg(
+        long,
+        value_name = "REGEX",
+        help = "Only consider files whose name matches this regular expression"
+    )]
+    ext: Option<String>,
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Exclude paths whose full path or file name matches this glob, can be repeated"
+    )]
+    exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the duplicate report to this file"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "json",
+        help = "Format used for --output"
+    )]
+    format: OutputFormat,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "first",
+        help = "Which file in a duplicate group to keep"
+    )]
+    keep: KeepPolicy,
+    #[arg(long, help = "Skip the confirmation prompt")]
+    yes: bool,
+    #[arg(long, help = "Print what would be removed without deleting anything")]
+    dry_run: bool,
+    #[arg(
+        long,
+        help = "Follow symlinked directories and files instead of skipping them"
+    )]
+    follow_symlinks: bool,
 }
 
 /// Table row.
@@ -45,10 +98,168 @@ struct Row {
     dup_to: String,
 }
 
+/// Format used to serialize the duplicate report written by `--output`.
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+/// A single equivalence class of byte-identical files, as written to a report file.
+#[derive(Serialize)]
+struct DuplicateGroup {
+    id: usize,
+    size: u64,
+    hash: String,
+    paths: Vec<PathBuf>,
+}
+
+/// Which file in a duplicate group should survive when removing duplicates.
+#[derive(Clone, Copy, ValueEnum)]
+enum KeepPolicy {
+    First,
+    Oldest,
+    Newest,
+}
+
+impl KeepPolicy {
+    /// Picks the file within `paths` (already sorted alphabetically) that should be kept.
+    fn survivor<'a>(self, paths: &'a [PathBuf]) -> Result<&'a PathBuf> {
+        if let KeepPolicy::First = self {
+            return Ok(&paths[0]);
+        }
+
+        let mut survivor = &paths[0];
+        let mut survivor_modified = survivor
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("Failed to read modified time for {}", survivor.display()))?;
+        for path in &paths[1..] {
+            let modified = path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .with_context(|| format!("Failed to read modified time for {}", path.display()))?;
+            let replace = match self {
+                KeepPolicy::Oldest => modified < survivor_modified,
+                KeepPolicy::Newest => modified > survivor_modified,
+                KeepPolicy::First => unreachable!(),
+            };
+            if replace {
+                survivor = path;
+                survivor_modified = modified;
+            }
+        }
+
+        Ok(survivor)
+    }
+}
+
+/// Filters applied while discovering candidate files.
+struct Filters {
+    min_size: Option<u64>,
+    ext: Option<Regex>,
+    exclude: Vec<Pattern>,
+}
+
+impl Filters {
+    /// Builds filters from the raw CLI arguments, compiling the regex and globs up front.
+    fn new(min_size: Option<u64>, ext: Option<&str>, exclude: &[String]) -> Result<Self> {
+        let ext = ext
+            .map(|ext| Regex::new(ext).with_context(|| format!("Invalid regex {ext}")))
+            .transpose()?;
+        let exclude = exclude
+            .iter()
+            .map(|glob| Pattern::new(glob).with_context(|| format!("Invalid glob {glob}")))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            min_size,
+            ext,
+            exclude,
+        })
+    }
+
+    /// Whether `path` should be pruned from traversal entirely, regardless of whether it is a file or directory.
+    ///
+    /// A pattern matches if it matches the full path (so a glob like `photos/cache` targets that one location when
+    /// `photos` is the directory given) or just the final component (so a bare name like `node_modules` excludes it at any depth).
+    fn is_excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().and_then(|name| name.to_str());
+        self.exclude.iter().any(|pattern| {
+            pattern.matches_path(path) || name.is_some_and(|name| pattern.matches(name))
+        })
+    }
+
+    /// Whether a file should be kept as a duplicate candidate.
+    fn accepts(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        if self
+            .min_size
+            .is_some_and(|min_size| metadata.len() < min_size)
+        {
+            return false;
+        }
+
+        if let Some(ext) = &self.ext {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            if !ext.is_match(name) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Returns the `(dev, ino)` pair identifying the underlying file `metadata` points to, or `None` on platforms where
+/// that information isn't available.
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Mutable state threaded through a directory walk.
+///
+/// `visited` holds the canonicalized target of every symlink followed so far, file or directory, so `--follow-symlinks`
+/// cannot loop forever on a cyclic link. `inodes` holds the `(dev, ino)` of every file accepted as a candidate so far,
+/// so a hardlink to a file already discovered is recognized as the same underlying file rather than a duplicate.
+struct WalkState {
+    follow_symlinks: bool,
+    visited: HashSet<PathBuf>,
+    inodes: HashSet<(u64, u64)>,
+}
+
+impl WalkState {
+    fn new(follow_symlinks: bool) -> Self {
+        Self {
+            follow_symlinks,
+            visited: HashSet::new(),
+            inodes: HashSet::new(),
+        }
+    }
+}
+
 /// Retrieves list of files in a directory.
 ///
-/// If `recursive` is specified, all subdirectories are searched as well. Any error is propagated with added context.
-fn get_files<P>(dir: P, recursive: bool) -> Result<Vec<PathBuf>>
+/// If `recursive` is specified, all subdirectories are searched as well. `filters` are consulted before a path is
+/// pushed so excluded directories are never descended into and rejected files never make it into the candidate set.
+/// Symlinks are skipped unless `state.follow_symlinks` is set, in which case cycles are broken by tracking the
+/// canonical target of every symlink followed. Files that are hardlinks to one already seen are skipped, as
+/// they are the same underlying file rather than a duplicate. Any error is propagated with added context.
+fn get_files<P>(
+    dir: P,
+    recursive: bool,
+    filters: &Filters,
+    state: &mut WalkState,
+) -> Result<Vec<PathBuf>>
 where
     P: AsRef<Path>,
 {
@@ -61,9 +272,38 @@ where
             .with_context(|| format!("Error while reading directory {}", dir.display()))?
             .path();
 
+        if filters.is_excluded(&path) {
+            continue;
+        }
+
+        let link_metadata = fs::symlink_metadata(&path)
+            .with_context(|| format!("Failed to read metadata for {}", path.display()))?;
+        if link_metadata.is_symlink() {
+            if !state.follow_symlinks {
+                continue;
+            }
+
+            let real_path = fs::canonicalize(&path)
+                .with_context(|| format!("Failed to resolve symlink {}", path.display()))?;
+            if !state.visited.insert(real_path) {
+                continue;
+            }
+        }
+
         if recursive && path.is_dir() {
-            files.append(&mut get_files(path, true)?);
+            files.append(&mut get_files(&path, true, filters, state)?);
         } else if path.is_file() {
+            let metadata = path
+                .metadata()
+                .with_context(|| format!("Failed to read metadata for {}", path.display()))?;
+            if !filters.accepts(&path, &metadata) {
+                continue;
+            }
+            if let Some(inode) = inode(&metadata) {
+                if !state.inodes.insert(inode) {
+                    continue;
+                }
+            }
             files.push(path);
         }
     }
@@ -72,140 +312,296 @@ where
     Ok(files)
 }
 
-/// Makes pairs from list of all files for comparison.
-///
-/// If `cross` is `true` files within a single directory are also compared.
-fn get_pairs(all_files: &[Vec<PathBuf>], cross: bool) -> Vec<(&PathBuf, &PathBuf)> {
-    let mut pairs = Vec::new();
-
-    if !cross {
-        pairs.append(
-            &mut all_files
-                .iter() // Iterate through all directories.
-                .flat_map(|files| files.iter().combinations(2).collect_vec()) // Get pairs of files in each directory.
-                .map(|f| (f[0], f[1])) // Convert vector to tuple.
-                .collect(),
-        );
-    }
-
-    pairs.append(
-        &mut all_files
-            .iter() // Iterate through all directories.
-            .combinations(2) // Get pairs of directories.
-            .flat_map(|dirs| dirs[0].iter().cartesian_product(dirs[1]).collect_vec()) // For each such pair get cartesian product of files.
-            .collect(),
+/// Builds and styles a progress bar with the given length and leading message.
+fn new_progress_bar(len: u64, message: &str) -> Result<ProgressBar> {
+    let bar = ProgressBar::new(len);
+    bar.set_style(
+        ProgressStyle::with_template(&format!(
+            "{message} {{bar:40.white/white.dim}} {{pos}}/{{len}}"
+        ))
+        .context("Failed to set progress bar style")?
+        .progress_chars("━╸━"),
     );
+    Ok(bar)
+}
 
-    pairs
+/// Computes a hash over the first `CHUNK_SIZE` bytes of a file, or the whole file if it is shorter.
+fn partial_hash(path: &Path) -> Result<blake3::Hash> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open file {}", path.display()))?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file
+            .read(&mut buf[filled..])
+            .with_context(|| format!("Error while reading file {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+
+    Ok(blake3::hash(&buf[..filled]))
 }
 
-/// Checks if two files are same.
-///
-/// Performs a byte for byte comparison with early exit in case of differences.
-fn is_same<P, Q>(file1: P, file2: Q) -> Result<bool>
+/// Computes a hash over the entire contents of a file, read in `CHUNK_SIZE` chunks.
+fn full_hash(path: &Path) -> Result<blake3::Hash> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open file {}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .with_context(|| format!("Error while reading file {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Hashes `items` concurrently with `hash_fn`, driving `bar` via an atomic counter as results come in.
+fn hash_all<T, F>(
+    items: Vec<(T, PathBuf)>,
+    bar: &ProgressBar,
+    hash_fn: F,
+) -> Result<Vec<(T, PathBuf, blake3::Hash)>>
 where
-    P: AsRef<Path>,
-    Q: AsRef<Path>,
+    T: Send,
+    F: Fn(&Path) -> Result<blake3::Hash> + Sync,
 {
-    let file1 = file1.as_ref();
-    let file2 = file2.as_ref();
+    let done = AtomicU64::new(0);
+    items
+        .into_par_iter()
+        .map(|(key, path)| {
+            let hash = hash_fn(&path)?;
+            bar.set_position(done.fetch_add(1, Ordering::Relaxed) + 1);
+            Ok((key, path, hash))
+        })
+        .collect()
+}
 
-    if file1.metadata()?.len() != file2.metadata()?.len() {
-        return Ok(false);
+/// Groups files that are byte-for-byte identical using a size, then partial hash, then full hash pipeline.
+///
+/// Files are first bucketed by `metadata().len()`, discarding buckets of a single file since they cannot contain
+/// duplicates. Each remaining bucket is split by a partial hash covering at most `CHUNK_SIZE` bytes, again
+/// discarding singletons, and each surviving partial-hash bucket is split by a hash of the whole file. Every file is
+/// read at most twice. Collisions are astronomically unlikely with blake3, so a matching full hash is taken as proof
+/// of byte-exact equality. Both hashing stages are computed concurrently across buckets with rayon.
+fn group_duplicates(
+    candidates: Vec<(usize, PathBuf)>,
+) -> Result<Vec<(blake3::Hash, Vec<(usize, PathBuf)>)>> {
+    let mut by_size: HashMap<u64, Vec<(usize, PathBuf)>> = HashMap::new();
+    for (dir, path) in candidates {
+        let len = path
+            .metadata()
+            .with_context(|| format!("Failed to read metadata for {}", path.display()))?
+            .len();
+        by_size.entry(len).or_default().push((dir, path));
     }
+    by_size.retain(|_, files| files.len() > 1);
 
-    let mut fp1 =
-        File::open(file1).with_context(|| format!("Failed to open file {}", file1.display()))?;
-    let mut fp2 =
-        File::open(file2).with_context(|| format!("Failed to open file {}", file2.display()))?;
-    let mut buf1 = vec![0; CHUNK_SIZE];
-    let mut buf2 = vec![0; CHUNK_SIZE];
+    // Carry the size alongside each file so partial-hash grouping stays scoped to its size bucket.
+    let sized: Vec<((u64, usize), PathBuf)> = by_size
+        .into_iter()
+        .flat_map(|(size, files)| {
+            files
+                .into_iter()
+                .map(move |(dir, path)| ((size, dir), path))
+        })
+        .collect();
 
-    loop {
-        let n1 = fp1
-            .read(&mut buf1)
-            .with_context(|| format!("Error while reading file {}", file1.display()))?;
-        let n2 = fp2
-            .read(&mut buf2)
-            .with_context(|| format!("Error while reading file {}", file2.display()))?;
-
-        if n1 != n2 {
-            return Ok(false);
-        }
+    let bar = new_progress_bar(sized.len() as u64, "Computing partial hashes")?;
+    let mut by_partial: HashMap<(u64, blake3::Hash), Vec<(usize, PathBuf)>> = HashMap::new();
+    for ((size, dir), path, hash) in hash_all(sized, &bar, partial_hash)? {
+        by_partial
+            .entry((size, hash))
+            .or_default()
+            .push((dir, path));
+    }
+    bar.finish();
+    eprintln!(); // TODO: indicatiff has a bug where it does not print a new line after finishing. Once it is fixed update indicatiff and remove this line.
 
-        if n1 == 0 {
-            break;
-        }
+    // Tag each file with the index of its partial-hash bucket so full-hash grouping stays scoped to it.
+    let partial_groups: Vec<Vec<(usize, PathBuf)>> = by_partial
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .collect();
+    let bucketed: Vec<((usize, usize), PathBuf)> = partial_groups
+        .into_iter()
+        .enumerate()
+        .flat_map(|(group, files)| {
+            files
+                .into_iter()
+                .map(move |(dir, path)| ((group, dir), path))
+        })
+        .collect();
+
+    let bar = new_progress_bar(bucketed.len() as u64, "Computing full hashes")?;
+    let mut by_full: HashMap<(usize, blake3::Hash), Vec<(usize, PathBuf)>> = HashMap::new();
+    for ((group, dir), path, hash) in hash_all(bucketed, &bar, full_hash)? {
+        by_full.entry((group, hash)).or_default().push((dir, path));
+    }
+    bar.finish();
+    eprintln!(); // TODO: see above.
+
+    Ok(by_full
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|((_, hash), files)| (hash, files))
+        .collect())
+}
 
-        if buf1[..n1] != buf2[..n2] {
-            return Ok(false);
+/// Writes the discovered duplicate groups to `path`, encoded with `format`.
+fn write_report(path: &Path, format: OutputFormat, groups: &[DuplicateGroup]) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            serde_json::to_writer_pretty(file, groups)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_path(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            writer
+                .write_record(["id", "size", "hash", "paths"])
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            for group in groups {
+                let paths = group.paths.iter().map(|path| path.display()).join(";");
+                writer
+                    .write_record([
+                        group.id.to_string(),
+                        group.size.to_string(),
+                        group.hash.clone(),
+                        paths,
+                    ])
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            writer
+                .flush()
+                .with_context(|| format!("Failed to write {}", path.display()))?;
         }
     }
 
-    Ok(true)
+    Ok(())
 }
 
 /// Finds duplicate files and displays them.
 ///
 /// Also prompts for their removal.
-fn dup(dirs: Vec<PathBuf>, cross: bool, recursive: bool) -> Result<()> {
+/// The prompt is skipped when `yes` is set; with `dry_run` nothing is prompted for or removed.
+fn dup(
+    dirs: Vec<PathBuf>,
+    cross: bool,
+    recursive: bool,
+    filters: &Filters,
+    output: Option<&Path>,
+    format: OutputFormat,
+    keep: KeepPolicy,
+    yes: bool,
+    dry_run: bool,
+    follow_symlinks: bool,
+) -> Result<()> {
     let dirs = dirs.into_iter();
-    let files: Result<Vec<_>> = dirs.map(|d| get_files(d, recursive)).collect();
+    // Shared across every root directory so a hardlink discovered under one argument is recognized as the same
+    // underlying file as its sibling under another, which matters most for `--cross`.
+    let mut state = WalkState::new(follow_symlinks);
+    let files: Result<Vec<_>> = dirs
+        .map(|d| get_files(d, recursive, filters, &mut state))
+        .collect();
     let files = files?;
-    let pairs = get_pairs(&files, cross);
-
-    let mut dups = HashMap::new();
-    let bar = ProgressBar::new(
-        pairs
-            .len()
-            .try_into()
-            .with_context(|| format!("Could not convert {} from usize to u64", pairs.len()))?,
-    );
-    bar.set_style(
-        ProgressStyle::with_template("Checking files {bar:40.white/white.dim} {pos}/{len}")
-            .context("Failed to set progress bar style")?
-            .progress_chars("━╸━"),
-    );
-    for (file1, file2) in pairs {
-        if dups.contains_key(file1) || dups.contains_key(file2) {
-            bar.inc(1);
-            continue;
-        }
 
-        if is_same(file1, file2)? {
-            dups.insert(file2, file1);
-        }
+    // Tag every file with the index of the directory it came from so `--cross` can be honoured after grouping.
+    let candidates: Vec<(usize, PathBuf)> = files
+        .into_iter()
+        .enumerate()
+        .flat_map(|(dir, paths)| paths.into_iter().map(move |path| (dir, path)))
+        .collect();
 
-        bar.inc(1);
+    let groups: Vec<(blake3::Hash, Vec<PathBuf>)> = group_duplicates(candidates)?
+        .into_iter()
+        // With `--cross`, a group only counts if it spans more than one directory. Once a group qualifies, every
+        // non-survivor in it is still removed below, even ones that share a directory with another duplicate:
+        // `--cross` decides which equivalence classes count, not which files within a counted class survive.
+        .filter(|(_, group)| !cross || group.iter().map(|(dir, _)| *dir).unique().count() > 1)
+        .map(|(hash, group)| {
+            (
+                hash,
+                group.into_iter().map(|(_, path)| path).sorted().collect(),
+            )
+        })
+        .collect();
+
+    if let Some(output) = output {
+        let report = groups
+            .iter()
+            .enumerate()
+            .map(|(id, (hash, paths))| {
+                let size = paths[0]
+                    .metadata()
+                    .with_context(|| format!("Failed to read metadata for {}", paths[0].display()))?
+                    .len();
+                Ok(DuplicateGroup {
+                    id,
+                    size,
+                    hash: hash.to_hex().to_string(),
+                    paths: paths.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        write_report(output, format, &report)?;
     }
-    bar.finish();
-    eprintln!(); // TODO: indicatiff has a bug where it does not print a new line after finishing. Once it is fixed update indicatiff and remove this line.
 
-    if dups.is_empty() {
+    if groups.is_empty() {
         println!("No duplicates found");
-    } else {
-        let data = dups
-            .iter()
-            .sorted()
-            .map(|r| Row {
-                dup: r.0.display().to_string(),
-                dup_to: r.1.display().to_string(),
+        return Ok(());
+    }
+
+    // Resolve one survivor per group up front so the table, prompt and removal agree on what gets kept.
+    let plan: Vec<(&PathBuf, Vec<&PathBuf>)> = groups
+        .iter()
+        .map(|(_, group)| {
+            let survivor = keep.survivor(group)?;
+            let duplicates = group.iter().filter(|path| *path != survivor).collect();
+            Ok((survivor, duplicates))
+        })
+        .collect::<Result<_>>()?;
+
+    let data = plan
+        .iter()
+        .flat_map(|(survivor, duplicates)| {
+            duplicates.iter().map(move |dup| Row {
+                dup: dup.display().to_string(),
+                dup_to: survivor.display().to_string(),
             })
-            .collect_vec();
-        let mut table = Table::new(data);
-        let style = Style::sharp().remove_frame().remove_vertical();
-        table.with(style).with(Width::wrap::<usize>(
-            termsize::get()
-                .unwrap_or(Size { rows: 0, cols: 80 })
-                .cols
-                .into(),
-        ));
-        table.modify(Rows::first(), Color::BOLD);
-        table.modify(Columns::first(), Padding::new(0, 2, 0, 0));
-        table.modify(Columns::last(), Padding::new(2, 0, 0, 0));
-        println!("\n{table}");
-
-        print!("\nRemove {} duplicates? [y/N] ", dups.len());
+        })
+        .collect_vec();
+    let mut table = Table::new(data);
+    let style = Style::sharp().remove_frame().remove_vertical();
+    table.with(style).with(Width::wrap::<usize>(
+        termsize::get()
+            .unwrap_or(Size { rows: 0, cols: 80 })
+            .cols
+            .into(),
+    ));
+    table.modify(Rows::first(), Color::BOLD);
+    table.modify(Columns::first(), Padding::new(0, 2, 0, 0));
+    table.modify(Columns::last(), Padding::new(2, 0, 0, 0));
+    println!("\n{table}");
+
+    let count: usize = plan.iter().map(|(_, duplicates)| duplicates.len()).sum();
+
+    if dry_run {
+        println!("\nWould remove {count} duplicates (dry run, nothing deleted)");
+        return Ok(());
+    }
+
+    if !yes {
+        print!("\nRemove {count} duplicates? [y/N] ");
         io::stdout().flush().context("Failed to flush stdout")?;
         let mut choice = String::new();
         io::stdin()
@@ -213,12 +609,16 @@ fn dup(dirs: Vec<PathBuf>, cross: bool, recursive: bool) -> Result<()> {
             .context("Failed to read user input")?;
         choice = choice.to_lowercase();
         let choice = choice.trim();
-        if choice == "y" || choice == "yes" {
-            println!("Removing duplicates...");
-            for dup in dups.keys() {
-                fs::remove_file(dup)
-                    .with_context(|| format!("Failed to remove file {}", dup.display()))?;
-            }
+        if choice != "y" && choice != "yes" {
+            return Ok(());
+        }
+    }
+
+    println!("Removing duplicates...");
+    for (_, duplicates) in &plan {
+        for dup in duplicates {
+            fs::remove_file(dup)
+                .with_context(|| format!("Failed to remove file {}", dup.display()))?;
         }
     }
 
@@ -233,7 +633,26 @@ fn main() {
         return;
     }
 
-    if let Err(e) = dup(args.dirs, args.cross, args.recursive) {
+    let filters = match Filters::new(args.min_size, args.ext.as_deref(), &args.exclude) {
+        Ok(filters) => filters,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+
+    if let Err(e) = dup(
+        args.dirs,
+        args.cross,
+        args.recursive,
+        &filters,
+        args.output.as_deref(),
+        args.format,
+        args.keep,
+        args.yes,
+        args.dry_run,
+        args.follow_symlinks,
+    ) {
         eprintln!("{e}");
     }
 }